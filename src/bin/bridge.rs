@@ -0,0 +1,45 @@
+use clap::{Arg, Command, value_parser};
+use scopehal_fx_bridge::{
+    bridge::{BridgeServer, SessionOptions},
+    fx3lafw::setup_device,
+    logbuffer::BufferingLogger,
+};
+use std::error::Error;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    // Retain the tail of diagnostics in memory so `dump_log` can report it,
+    // while still forwarding everything to env_logger.
+    let inner = env_logger::Builder::from_default_env().build();
+    let (logger, _) = BufferingLogger::new(Box::new(inner), 1024);
+    let log_buffer = logger.init()?;
+
+    let matches = Command::new("bridge")
+        .about("Expose FX3LAFW over the scopehal two-socket bridge protocol")
+        .arg(
+            Arg::new("control")
+                .short('c')
+                .long("control")
+                .value_parser(value_parser!(String))
+                .default_value("127.0.0.1:5025")
+                .help("Address of the text control socket"),
+        )
+        .arg(
+            Arg::new("data")
+                .short('d')
+                .long("data")
+                .value_parser(value_parser!(String))
+                .default_value("127.0.0.1:5026")
+                .help("Address of the binary data socket"),
+        )
+        .get_matches();
+
+    let control = matches.get_one::<String>("control").unwrap();
+    let data = matches.get_one::<String>("data").unwrap();
+
+    let device = setup_device()?;
+    let mut server =
+        BridgeServer::new(device, SessionOptions::default()).with_log_buffer(log_buffer);
+    server.serve(control, data)?;
+
+    Ok(())
+}
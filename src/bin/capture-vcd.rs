@@ -1,4 +1,5 @@
-use clap::{Arg, Command, value_parser};
+use clap::{Arg, ArgAction, Command, value_parser};
+use scopehal_fx_bridge::config::Config;
 use scopehal_fx_bridge::fx3lafw::{acquisition, setup_device};
 use status_line::StatusLine;
 use std::{
@@ -17,23 +18,33 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     let mut builder = Command::new("capture-vcd")
         .about("Capture VCD data from FX3LAFW")
+        .arg(
+            Arg::new("config")
+                .short('C')
+                .long("config")
+                .value_parser(value_parser!(PathBuf))
+                .default_value("capture.conf")
+                .help("Configuration file with saved defaults"),
+        )
         .arg(
             Arg::new("output")
                 .short('o')
                 .long("output")
                 .value_parser(value_parser!(PathBuf))
-                .default_value("capture.vcd")
-                .required(true)
                 .help("Output file"),
         )
         .arg(
             Arg::new("samplerate")
                 .short('s')
                 .long("samplerate")
-                .default_value("48")
                 .value_parser(["48", "96", "192"])
-                .required(true)
                 .help("Sample rate in MHz"),
+        )
+        .arg(
+            Arg::new("save-config")
+                .long("save-config")
+                .action(ArgAction::SetTrue)
+                .help("Write the resolved configuration back to the config file and exit"),
         );
 
     for i in 0..32 {
@@ -48,25 +59,54 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     let matches = builder.get_matches();
 
-    let output = matches.get_one::<PathBuf>("output").unwrap();
-    let sample_rate = matches
-        .get_one::<String>("samplerate")
-        .unwrap()
-        .parse::<usize>()
-        .unwrap();
+    // Load saved defaults, then let command-line flags override them.
+    let config_path = matches.get_one::<PathBuf>("config").unwrap();
+    let config = Config::read(config_path)?;
+
+    let output = matches
+        .get_one::<PathBuf>("output")
+        .cloned()
+        .unwrap_or(config.output);
+    let sample_rate = match matches.get_one::<String>("samplerate") {
+        Some(value) => value.parse::<usize>().unwrap(),
+        None => config.samplerate,
+    };
 
     let mut channels = Vec::new();
     for i in 0..32 {
-        if let Some(name) = matches.get_one::<String>(&format!("{}", i)) {
+        let name = matches
+            .get_one::<String>(&format!("{}", i))
+            .cloned()
+            .or_else(|| config.channels.get(i).cloned().flatten());
+        if let Some(name) = name {
             if channels.len() <= i {
                 channels.resize((i + 1).div_ceil(8) * 8, None);
             }
-            channels[i] = Some(name.to_string());
+            channels[i] = Some(name);
         }
     }
 
+    // The configured sample size (bus width) is honoured, but widened so every
+    // named channel fits, and the channel table is sized to match.
+    let sample_size = config.sample_size.max(channels.len());
+    channels.resize(sample_size, None);
+
+    // Persist the resolved defaults so a named probe layout can be saved once
+    // and reused without retyping the channel flags.
+    if matches.get_flag("save-config") {
+        let saved = Config {
+            channels: channels.clone(),
+            samplerate: sample_rate,
+            sample_size,
+            output: output.clone(),
+        };
+        saved.write(config_path)?;
+        eprintln!("Saved configuration to {}", config_path.display());
+        return Ok(());
+    }
+
     let device = setup_device()?;
-    let acquisition = acquisition(&device, sample_rate, channels.len())?;
+    let acquisition = acquisition(&device, sample_rate, sample_size, None)?;
 
     #[derive(Clone)]
     struct Progress {
@@ -0,0 +1,371 @@
+//! A two-socket bridge server that exposes the FX3 logic analyzer the way
+//! scopehal-apps expects to talk to an instrument: a line-oriented text
+//! control socket and a separate binary data socket.
+//!
+//! The control socket accepts one command per line and answers with a single
+//! line (`OK ...` / `ERR ...`). Arming an acquisition starts streaming the raw
+//! little-endian sample words produced by [`AcquisitionHandle`] down the data
+//! socket as length-framed blocks (a `u32` little-endian byte count followed by
+//! that many bytes), so a client can pull waveforms live instead of reading a
+//! finished VCD/FST file.
+//!
+//! The dispatch loop mirrors a diagnostic-server-over-channel design: the
+//! [`BridgeServer`] owns the [`nusb::Device`], parses each line into a
+//! [`BridgeCommand`], and the handler serializes one command at a time. Because
+//! the device is held for the lifetime of the server, acquisitions can be
+//! armed and stopped repeatedly without re-running the whole program.
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc,
+    },
+    thread,
+    time::Duration,
+};
+
+use nusb::Device;
+
+use crate::fx3lafw::{self, AcquisitionHandle, get_version};
+use crate::logbuffer::LogBuffer;
+
+/// Sample rates (in MHz) that `fx3lafw::encode_start_flags` accepts.
+const SUPPORTED_SAMPLERATES: &[usize] = &[30, 48, 192];
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("io error: {0}")]
+    IoError(std::io::Error),
+
+    #[error("fx3lafw error: {0}")]
+    Fx3lafwError(fx3lafw::Error),
+
+    #[error("unknown command: {0}")]
+    UnknownCommand(String),
+
+    #[error("malformed command: {0}")]
+    MalformedCommand(String),
+
+    #[error("unsupported sample rate: {0}")]
+    UnsupportedSampleRate(usize),
+
+    #[error("unsupported sample size: {0}")]
+    UnsupportedSampleSize(usize),
+
+    #[error("no acquisition is armed")]
+    NotArmed,
+}
+
+/// Per-session options negotiated once and applied to both sockets.
+#[derive(Debug, Clone, Copy)]
+pub struct SessionOptions {
+    /// Write timeout applied to the control socket. No read timeout is set:
+    /// the control socket is long-lived and must block on `read_line` waiting
+    /// for the next command without aborting an idle session.
+    pub control_timeout: Duration,
+    /// Write timeout applied to the data socket.
+    pub data_timeout: Duration,
+    /// Whether to set `TCP_NODELAY` on the accepted sockets. std exposes no
+    /// portable `SO_KEEPALIVE`, so only nodelay is offered here.
+    pub nodelay: bool,
+}
+
+impl Default for SessionOptions {
+    fn default() -> Self {
+        SessionOptions {
+            control_timeout: Duration::from_secs(30),
+            data_timeout: Duration::from_secs(5),
+            nodelay: true,
+        }
+    }
+}
+
+/// One command received on the control socket.
+#[derive(Debug, Clone)]
+pub enum BridgeCommand {
+    /// Query the firmware version.
+    GetVersion,
+    /// Select the sample rate in MHz.
+    SampleRate(usize),
+    /// Set the channel count / sample size in bits.
+    SampleSize(usize),
+    /// Start streaming the currently configured acquisition.
+    Arm,
+    /// Stop the running acquisition.
+    Stop,
+    /// Report the tail of the in-memory diagnostic log.
+    DumpLog,
+}
+
+impl BridgeCommand {
+    /// Parse one line from the control socket into a [`BridgeCommand`].
+    pub fn parse(line: &str) -> Result<BridgeCommand, Error> {
+        let mut parts = line.split_whitespace();
+        let verb = parts.next().unwrap_or("");
+        match verb {
+            "get_version" => Ok(BridgeCommand::GetVersion),
+            "samplerate" => {
+                let value = parts
+                    .next()
+                    .ok_or_else(|| Error::MalformedCommand(line.to_string()))?
+                    .parse::<usize>()
+                    .map_err(|_| Error::MalformedCommand(line.to_string()))?;
+                Ok(BridgeCommand::SampleRate(value))
+            }
+            "sample_size" => {
+                let value = parts
+                    .next()
+                    .ok_or_else(|| Error::MalformedCommand(line.to_string()))?
+                    .parse::<usize>()
+                    .map_err(|_| Error::MalformedCommand(line.to_string()))?;
+                Ok(BridgeCommand::SampleSize(value))
+            }
+            "arm" => Ok(BridgeCommand::Arm),
+            "stop" => Ok(BridgeCommand::Stop),
+            "dump_log" => Ok(BridgeCommand::DumpLog),
+            other => Err(Error::UnknownCommand(other.to_string())),
+        }
+    }
+}
+
+/// A bridge server owning a single FX3 device.
+pub struct BridgeServer {
+    device: Device,
+    options: SessionOptions,
+
+    sample_rate_mhz: usize,
+    sample_size: usize,
+
+    /// The stop flag of the acquisition currently streaming on the data
+    /// socket, if any.
+    running: Option<Arc<AtomicBool>>,
+
+    /// The worker threads of the currently streaming acquisition, if any, so
+    /// they can be joined before the next acquisition is armed.
+    pump: Option<DataPump>,
+
+    /// Optional in-memory log ring queried by the `dump_log` command.
+    log_buffer: Option<LogBuffer>,
+}
+
+/// The two worker threads backing one streaming acquisition: a producer that
+/// drains the [`AcquisitionHandle`] into framed blocks and a writer that pushes
+/// them to the data socket.
+struct DataPump {
+    producer: thread::JoinHandle<()>,
+    writer: thread::JoinHandle<()>,
+}
+
+impl BridgeServer {
+    /// Create a server that drives `device` with the given session options.
+    pub fn new(device: Device, options: SessionOptions) -> BridgeServer {
+        BridgeServer {
+            device,
+            options,
+            sample_rate_mhz: 48,
+            sample_size: 16,
+            running: None,
+            pump: None,
+            log_buffer: None,
+        }
+    }
+
+    /// Attach a [`LogBuffer`] so `dump_log` can report recent diagnostics.
+    pub fn with_log_buffer(mut self, log_buffer: LogBuffer) -> BridgeServer {
+        self.log_buffer = Some(log_buffer);
+        self
+    }
+
+    /// Listen on `control_addr` for the text control socket and
+    /// `data_addr` for the binary data socket, serving a single client.
+    ///
+    /// The data socket is accepted first so that an `arm` command can start
+    /// streaming immediately.
+    pub fn serve(&mut self, control_addr: &str, data_addr: &str) -> Result<(), Error> {
+        let control = TcpListener::bind(control_addr).map_err(Error::IoError)?;
+        let data = TcpListener::bind(data_addr).map_err(Error::IoError)?;
+        log::info!("bridge control socket on {control_addr}, data socket on {data_addr}");
+
+        let (data_stream, _) = data.accept().map_err(Error::IoError)?;
+        let (control_stream, peer) = control.accept().map_err(Error::IoError)?;
+        log::info!("bridge client connected from {peer}");
+
+        self.configure_socket(&control_stream, self.options.control_timeout)?;
+        self.configure_socket(&data_stream, self.options.data_timeout)?;
+
+        self.dispatch_loop(control_stream, data_stream)
+    }
+
+    fn configure_socket(&self, stream: &TcpStream, write_timeout: Duration) -> Result<(), Error> {
+        // Only a write timeout is applied. Deliberately no read timeout: the
+        // control socket must block indefinitely on `read_line` waiting for the
+        // next command, and a read timeout would surface as `WouldBlock` and
+        // abort the whole server after an idle period.
+        stream.set_write_timeout(Some(write_timeout)).map_err(Error::IoError)?;
+        if self.options.nodelay {
+            // `set_nodelay` keeps control-socket round-trips snappy; std offers
+            // no portable SO_KEEPALIVE, so that is all we enable here.
+            stream.set_nodelay(true).map_err(Error::IoError)?;
+        }
+        Ok(())
+    }
+
+    /// Read lines off the control socket and serialize their handling, one
+    /// command at a time, until the client disconnects.
+    fn dispatch_loop(
+        &mut self,
+        control_stream: TcpStream,
+        data_stream: TcpStream,
+    ) -> Result<(), Error> {
+        let mut reader = BufReader::new(control_stream.try_clone().map_err(Error::IoError)?);
+        let mut writer = control_stream;
+        let data_stream = Arc::new(data_stream);
+
+        let mut line = String::new();
+        loop {
+            line.clear();
+            let read = reader.read_line(&mut line).map_err(Error::IoError)?;
+            if read == 0 {
+                break;
+            }
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+
+            let response = match BridgeCommand::parse(trimmed) {
+                Ok(command) => match self.handle(command, &data_stream) {
+                    Ok(reply) => format!("OK {reply}"),
+                    Err(err) => format!("ERR {err}"),
+                },
+                Err(err) => format!("ERR {err}"),
+            };
+            writeln!(writer, "{response}").map_err(Error::IoError)?;
+            writer.flush().map_err(Error::IoError)?;
+        }
+
+        self.stop();
+        Ok(())
+    }
+
+    /// Handle a single parsed command and produce the text reply payload.
+    fn handle(
+        &mut self,
+        command: BridgeCommand,
+        data_stream: &Arc<TcpStream>,
+    ) -> Result<String, Error> {
+        match command {
+            BridgeCommand::GetVersion => {
+                let version = get_version(&self.device).map_err(Error::Fx3lafwError)?;
+                Ok(format!("{}.{}", version.major, version.minor))
+            }
+            BridgeCommand::SampleRate(rate) => {
+                if !SUPPORTED_SAMPLERATES.contains(&rate) {
+                    return Err(Error::UnsupportedSampleRate(rate));
+                }
+                self.sample_rate_mhz = rate;
+                Ok(format!("samplerate {rate}"))
+            }
+            BridgeCommand::SampleSize(size) => {
+                if !matches!(size, 8 | 16 | 24 | 32) {
+                    return Err(Error::UnsupportedSampleSize(size));
+                }
+                self.sample_size = size;
+                Ok(format!("sample_size {size}"))
+            }
+            BridgeCommand::Arm => {
+                self.stop();
+                let handle = fx3lafw::acquisition(
+                    &self.device,
+                    self.sample_rate_mhz,
+                    self.sample_size,
+                    None,
+                )
+                .map_err(Error::Fx3lafwError)?;
+                self.running = Some(handle.stop.clone());
+                self.pump = Some(spawn_data_pump(handle, data_stream.clone()));
+                Ok("armed".to_string())
+            }
+            BridgeCommand::Stop => {
+                if self.running.is_none() {
+                    return Err(Error::NotArmed);
+                }
+                self.stop();
+                Ok("stopped".to_string())
+            }
+            BridgeCommand::DumpLog => match &self.log_buffer {
+                Some(buffer) => {
+                    let records = buffer.snapshot();
+                    let tail = records
+                        .iter()
+                        .map(|r| format!("{} {}: {}", r.level, r.target, r.message))
+                        .collect::<Vec<_>>()
+                        .join("; ");
+                    Ok(format!("{} records: {tail}", records.len()))
+                }
+                None => Ok("0 records".to_string()),
+            },
+        }
+    }
+
+    /// Signal the currently running acquisition (if any) to stop and wait for
+    /// its pump threads to finish.
+    ///
+    /// Joining the old pump before returning is what lets acquisitions be
+    /// armed and stopped repeatedly: the next `arm` only spawns a new writer
+    /// once the previous one has released the data socket, so two threads never
+    /// interleave length-framed blocks onto the same stream.
+    fn stop(&mut self) {
+        if let Some(stop) = self.running.take() {
+            stop.store(true, Ordering::Relaxed);
+        }
+        if let Some(pump) = self.pump.take() {
+            // The producer exits once the iterator drains (the stop flag makes
+            // it terminate); dropping its `tx` then ends the writer.
+            let _ = pump.producer.join();
+            let _ = pump.writer.join();
+        }
+    }
+}
+
+/// Drain `handle` on a worker thread and write each sample word to the data
+/// socket as length-framed blocks of raw little-endian bytes.
+fn spawn_data_pump(handle: AcquisitionHandle, data_stream: Arc<TcpStream>) -> DataPump {
+    let sample_bytes = handle.sample_bytes;
+    // Coalesce words into modest blocks so we frame once per chunk rather than
+    // once per sample, matching the iterator's chunked delivery.
+    let words_per_block = (1024 * 1024) / sample_bytes.max(1);
+    let (tx, rx) = mpsc::channel::<Vec<u8>>();
+
+    let producer = thread::spawn(move || {
+        let mut block = Vec::with_capacity(words_per_block * sample_bytes);
+        for word in handle {
+            block.extend_from_slice(&word.to_le_bytes()[..sample_bytes]);
+            if block.len() >= words_per_block * sample_bytes {
+                if tx.send(std::mem::take(&mut block)).is_err() {
+                    return;
+                }
+            }
+        }
+        if !block.is_empty() {
+            let _ = tx.send(block);
+        }
+    });
+
+    let writer = thread::spawn(move || {
+        let mut stream = data_stream.as_ref();
+        while let Ok(block) = rx.recv() {
+            let len = block.len() as u32;
+            if stream.write_all(&len.to_le_bytes()).is_err() || stream.write_all(&block).is_err() {
+                log::error!("bridge data socket write failed, dropping acquisition");
+                break;
+            }
+        }
+        let _ = stream.flush();
+    });
+
+    DataPump { producer, writer }
+}
@@ -0,0 +1,124 @@
+//! A small persistent configuration store backed by a `key=value` text file.
+//!
+//! It lets a user save a named probe layout once (the 32 channel names, a
+//! default sample rate and sample size, an output path) and reuse it across
+//! captures instead of retyping 32 `--N name` flags. The CLIs load it as
+//! defaults that command-line flags then override.
+//!
+//! The format is deliberately forgiving: unknown keys are ignored, missing
+//! keys fall back to the built-in defaults exposed by [`Config::default`], and
+//! both short and long string values are accepted as written, apart from
+//! surrounding whitespace, which is trimmed from keys and values.
+
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("io error: {0}")]
+    IoError(std::io::Error),
+}
+
+/// Persisted capture defaults.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    /// Channel names, indexed 0..32; `None` for unnamed channels.
+    pub channels: Vec<Option<String>>,
+    /// Default sample rate in MHz.
+    pub samplerate: usize,
+    /// Default sample size in bits.
+    pub sample_size: usize,
+    /// Default output path.
+    pub output: PathBuf,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            channels: vec![None; 32],
+            samplerate: 48,
+            sample_size: 16,
+            output: PathBuf::from("capture.vcd"),
+        }
+    }
+}
+
+impl Config {
+    /// Read a configuration file, falling back to [`Config::default`] for any
+    /// key that is missing or unparseable. A missing file yields the defaults.
+    pub fn read(path: &Path) -> Result<Config, Error> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Config::default()),
+            Err(err) => return Err(Error::IoError(err)),
+        };
+
+        let mut config = Config::default();
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let key = key.trim();
+            let value = value.trim();
+
+            match key {
+                "samplerate" => {
+                    if let Ok(value) = value.parse() {
+                        config.samplerate = value;
+                    }
+                }
+                "sample_size" => {
+                    if let Ok(value) = value.parse() {
+                        config.sample_size = value;
+                    }
+                }
+                "output" => config.output = PathBuf::from(value),
+                _ => {
+                    if let Ok(channel) = key.parse::<usize>() {
+                        if channel < config.channels.len() {
+                            config.channels[channel] = Some(value.to_string());
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Write the configuration back out as `key=value` lines. Keys are written
+    /// in a stable order so that repeated writes produce tidy diffs.
+    pub fn write(&self, path: &Path) -> Result<(), Error> {
+        let mut entries: BTreeMap<String, String> = BTreeMap::new();
+        entries.insert("samplerate".to_string(), self.samplerate.to_string());
+        entries.insert("sample_size".to_string(), self.sample_size.to_string());
+        entries.insert("output".to_string(), self.output.to_string_lossy().into_owned());
+        for (channel, name) in self.channels.iter().enumerate() {
+            if let Some(name) = name {
+                entries.insert(channel.to_string(), name.clone());
+            }
+        }
+
+        let mut file = std::fs::File::create(path).map_err(Error::IoError)?;
+        for (key, value) in entries {
+            writeln!(file, "{key}={value}").map_err(Error::IoError)?;
+        }
+        Ok(())
+    }
+
+    /// Remove the configuration file. A missing file is not an error.
+    pub fn erase(path: &Path) -> Result<(), Error> {
+        match std::fs::remove_file(path) {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(Error::IoError(err)),
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use std::{
+    collections::VecDeque,
     sync::{
         Arc,
         atomic::{AtomicBool, AtomicU64, Ordering},
@@ -57,6 +58,132 @@ pub struct CmdStartAcquisition {
     pub sample_delay_l: u8,
 }
 
+/// Edge polarity of a single-channel transition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edge {
+    Rising,
+    Falling,
+    Either,
+}
+
+/// Static level of a single channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    High,
+    Low,
+}
+
+/// Condition a sample must satisfy for the trigger to fire.
+#[derive(Debug, Clone, Copy)]
+pub enum TriggerCondition {
+    /// An edge on `channel` with the given polarity.
+    Edge { channel: usize, edge: Edge },
+    /// A static level on `channel`.
+    Level { channel: usize, level: Level },
+    /// A multi-channel match of `pattern` over the bits selected by `mask`.
+    Pattern { mask: u32, pattern: u32 },
+}
+
+impl TriggerCondition {
+    /// Whether `word` satisfies the condition. `prev` is the previous sample
+    /// word, needed to detect edges (`None` before the first sample).
+    fn is_met(&self, word: u32, prev: Option<u32>) -> bool {
+        match *self {
+            TriggerCondition::Edge { channel, edge } => {
+                let Some(prev) = prev else { return false };
+                let now = (word >> channel) & 1;
+                let before = (prev >> channel) & 1;
+                match edge {
+                    Edge::Rising => before == 0 && now == 1,
+                    Edge::Falling => before == 1 && now == 0,
+                    Edge::Either => before != now,
+                }
+            }
+            TriggerCondition::Level { channel, level } => {
+                let bit = (word >> channel) & 1;
+                match level {
+                    Level::High => bit == 1,
+                    Level::Low => bit == 0,
+                }
+            }
+            TriggerCondition::Pattern { mask, pattern } => (word & mask) == (pattern & mask),
+        }
+    }
+}
+
+/// Counts edges of a given polarity on a single channel as the stream flows
+/// past, exposing the running total through a shared atomic.
+#[derive(Debug, Clone)]
+pub struct EdgeCounter {
+    pub channel: usize,
+    pub edge: Edge,
+    pub count: Arc<AtomicU64>,
+}
+
+impl EdgeCounter {
+    /// Create a counter for `channel`/`edge` starting at zero.
+    pub fn new(channel: usize, edge: Edge) -> EdgeCounter {
+        EdgeCounter {
+            channel,
+            edge,
+            count: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    fn update(&self, word: u32, prev: Option<u32>) {
+        let condition = TriggerCondition::Edge {
+            channel: self.channel,
+            edge: self.edge,
+        };
+        if condition.is_met(word, prev) {
+            self.count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Trigger configuration applied to an acquisition.
+///
+/// The firmware cannot evaluate arbitrary conditions, so the match itself and
+/// the pre-trigger window are handled on the host inside [`AcquisitionHandle`]'s
+/// iterator. The two delay counts are complementary, never overlapping:
+/// `pre_trigger` is retained host-side from the live stream, while
+/// `post_trigger` is encoded into the device's `sample_delay_h`/`sample_delay_l`
+/// field so the firmware bounds how long it keeps sampling after the trigger
+/// point (see [`Trigger::firmware_sample_delay`]).
+#[derive(Debug, Clone)]
+pub struct Trigger {
+    pub condition: TriggerCondition,
+    /// Samples to retain before the trigger point. Applied host-side.
+    pub pre_trigger: usize,
+    /// Samples to capture after the trigger point. Handed to the firmware via
+    /// `sample_delay_h`/`sample_delay_l`.
+    pub post_trigger: usize,
+    /// Optional free-running edge counter over the whole stream.
+    pub counter: Option<EdgeCounter>,
+}
+
+impl Trigger {
+    /// A trigger that fires on `condition` with no pre/post-trigger delay.
+    pub fn new(condition: TriggerCondition) -> Trigger {
+        Trigger {
+            condition,
+            pre_trigger: 0,
+            post_trigger: 0,
+            counter: None,
+        }
+    }
+
+    /// The post-trigger sample delay handed to the firmware, high byte first,
+    /// saturating at the 16-bit field width.
+    ///
+    /// Only the post-trigger count goes to the device: pre-trigger retention is
+    /// done on the host, so the two counts never apply to the same samples.
+    fn firmware_sample_delay(&self) -> (u8, u8) {
+        let delay = self.post_trigger.min(u16::MAX as usize) as u16;
+        ((delay >> 8) as u8, (delay & 0xff) as u8)
+    }
+}
+
 fn encode_start_flags(sample_rate_mhz: usize, sample_size: usize) -> u8 {
     let bit_superwide = 3;
     let _bit_clk_ctl2 = 4;
@@ -143,12 +270,17 @@ pub fn start_acquisition(
     device: &Device,
     sample_rate_mhz: usize,
     sample_size: usize,
+    trigger: Option<&Trigger>,
 ) -> Result<(), Error> {
     let flags = encode_start_flags(sample_rate_mhz, sample_size);
+    let (sample_delay_h, sample_delay_l) = match trigger {
+        Some(trigger) => trigger.firmware_sample_delay(),
+        None => (0, 0),
+    };
     let cmd = CmdStartAcquisition {
         flags,
-        sample_delay_h: 0,
-        sample_delay_l: 0,
+        sample_delay_h,
+        sample_delay_l,
     };
 
     let bytes_written = device
@@ -181,6 +313,7 @@ pub fn acquisition(
     device: &Device,
     sample_rate_mhz: usize,
     sample_size: usize,
+    trigger: Option<Trigger>,
 ) -> Result<AcquisitionHandle, Error> {
     device.set_configuration(1).map_err(Error::IoError)?;
     let interface = device.claim_interface(0).map_err(Error::IoError)?;
@@ -188,18 +321,29 @@ pub fn acquisition(
 
     let n_transfers = 16;
     let transfer_size = 1024 * 1024;
-
+    // Keep at least this many transfers in flight at all times, independent of
+    // how fast the consumer returns buffers. At 48-192 MHz the device FIFO
+    // overflows within a few transfers if nothing is posted, so when the
+    // consumer lags we fall back to a fresh allocation rather than letting the
+    // in-flight count reach zero.
+    let min_in_flight = n_transfers / 4;
+
+    // Pre-allocate the pool of backing buffers once, up front, and hand their
+    // storage to the queue. Completed buffers are later recycled through
+    // `return_*` instead of being dropped and reallocated on every transfer.
     while queue.pending() < n_transfers {
-        let request_buffer: RequestBuffer = RequestBuffer::new(transfer_size);
+        let request_buffer = RequestBuffer::reuse(Vec::with_capacity(transfer_size), transfer_size);
         let timer = std::time::Instant::now();
         queue.submit(request_buffer);
         log::debug!("submit in {:?}", timer.elapsed().as_micros());
     }
 
     eprintln!("sending start aquisition request...");
-    start_acquisition(device, sample_rate_mhz, sample_size)?;
+    start_acquisition(device, sample_rate_mhz, sample_size, trigger.as_ref())?;
 
     let (tx, rx) = mpsc::channel();
+    // Buffers the consumer has finished with, returned for resubmission.
+    let (return_tx, return_rx) = mpsc::channel::<Vec<u8>>();
 
     let recorded = Arc::new(AtomicU64::new(0));
     let recorded_clone = recorded.clone();
@@ -208,6 +352,28 @@ pub fn acquisition(
     let stop_clone = stop.clone();
     thread::spawn(move || {
         while !stop_clone.load(Ordering::Relaxed) {
+            // Top the queue back up with any buffers the consumer has drained,
+            // reusing their allocation rather than reaching for the allocator.
+            // Cap in-flight at the pool size so returns don't pile transfers up
+            // without bound.
+            while queue.pending() < n_transfers {
+                match return_rx.try_recv() {
+                    Ok(buffer) => queue.submit(RequestBuffer::reuse(buffer, transfer_size)),
+                    Err(_) => break,
+                }
+            }
+
+            // Reserve a minimum in-flight count independent of returns: if the
+            // consumer is lagging and hasn't handed buffers back yet, allocate
+            // fresh ones so the device FIFO keeps draining and no samples are
+            // dropped during the backpressure window. This only allocates while
+            // the consumer is behind; in steady state the recycled pool covers
+            // it. A fresh buffer returns through the same channel and rejoins
+            // the pool afterwards.
+            while queue.pending() < min_in_flight {
+                queue.submit(RequestBuffer::new(transfer_size));
+            }
+
             let timer = std::time::Instant::now();
             let completion = block_on(queue.next_complete());
             log::debug!("got completion in {:?}", timer.elapsed().as_micros());
@@ -215,17 +381,19 @@ pub fn acquisition(
                 log::error!("Error: {:?}", completion.status);
                 break;
             }
-            queue.submit(RequestBuffer::new(transfer_size));
             recorded_clone.fetch_add(
                 (transfer_size / (sample_size / 8)) as u64,
                 Ordering::Relaxed,
             );
-            tx.send(completion.data).unwrap();
+            if tx.send(completion.data).is_err() {
+                break;
+            }
         }
     });
 
     Ok(AcquisitionHandle {
         read_channel: rx,
+        return_channel: return_tx,
         stop: stop.clone(),
         sample_bytes: sample_size / 8,
 
@@ -233,11 +401,19 @@ pub fn acquisition(
         current_chunk_index: 0,
 
         recorded,
+
+        trigger,
+        triggered: false,
+        prev_word: None,
+        pre_trigger: VecDeque::new(),
     })
 }
 
 pub struct AcquisitionHandle {
     pub read_channel: mpsc::Receiver<Vec<u8>>,
+    /// Drained chunks are sent back here so the worker can resubmit their
+    /// storage instead of reallocating.
+    pub return_channel: mpsc::Sender<Vec<u8>>,
     pub stop: Arc<AtomicBool>,
     pub sample_bytes: usize,
 
@@ -245,15 +421,29 @@ pub struct AcquisitionHandle {
     pub current_chunk_index: usize,
 
     pub recorded: Arc<AtomicU64>,
-}
 
-impl Iterator for AcquisitionHandle {
-    type Item = u32;
+    /// Host-side trigger configuration; `None` captures free-running.
+    pub trigger: Option<Trigger>,
+    /// Whether the trigger has already fired and words are now flowing.
+    triggered: bool,
+    /// Previous raw word, kept for edge detection and edge counting.
+    prev_word: Option<u32>,
+    /// Words retained before the trigger point, bounded by `pre_trigger`.
+    pre_trigger: VecDeque<u32>,
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
+impl AcquisitionHandle {
+    /// Pull the next raw sample word off the USB chunks, advancing the edge
+    /// counter as it goes. Returns `None` once the worker thread is done.
+    fn next_raw(&mut self) -> Option<u32> {
         if self.current_chunk_index >= self.current_chunk.len() {
             if let Ok(chunk) = self.read_channel.recv() {
-                self.current_chunk = chunk;
+                // Hand the just-drained buffer back to the worker for reuse;
+                // ignore errors, which only mean the worker has already gone.
+                let drained = std::mem::replace(&mut self.current_chunk, chunk);
+                if !drained.is_empty() {
+                    let _ = self.return_channel.send(drained);
+                }
                 self.current_chunk_index = 0;
             } else {
                 return None;
@@ -265,6 +455,59 @@ impl Iterator for AcquisitionHandle {
         }
         self.current_chunk_index += self.sample_bytes;
 
+        if let Some(counter) = self.trigger.as_ref().and_then(|t| t.counter.as_ref()) {
+            counter.update(word, self.prev_word);
+        }
+
         Some(word)
     }
 }
+
+impl Iterator for AcquisitionHandle {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Once the pre-trigger window has been collected it is replayed first,
+        // oldest sample first, so the trigger point lands `pre_trigger` samples
+        // into the yielded stream (fewer if it fired before the window filled).
+        if self.triggered {
+            if let Some(word) = self.pre_trigger.pop_front() {
+                return Some(word);
+            }
+        }
+
+        loop {
+            let word = self.next_raw()?;
+            let prev = self.prev_word.replace(word);
+
+            if self.triggered {
+                return Some(word);
+            }
+
+            let Some(trigger) = self.trigger.as_ref() else {
+                // No trigger configured: everything is live immediately.
+                self.triggered = true;
+                return Some(word);
+            };
+
+            if trigger.condition.is_met(word, prev) {
+                self.triggered = true;
+                // Replay the retained pre-trigger samples, then this one.
+                if let Some(replayed) = self.pre_trigger.pop_front() {
+                    self.pre_trigger.push_back(word);
+                    return Some(replayed);
+                }
+                return Some(word);
+            }
+
+            // Not yet triggered: keep a sliding window of the last
+            // `pre_trigger` samples and discard the rest.
+            if trigger.pre_trigger > 0 {
+                if self.pre_trigger.len() == trigger.pre_trigger {
+                    self.pre_trigger.pop_front();
+                }
+                self.pre_trigger.push_back(word);
+            }
+        }
+    }
+}
@@ -0,0 +1,5 @@
+pub mod bridge;
+pub mod config;
+pub mod fx3_programmer;
+pub mod logbuffer;
+pub mod fx3lafw;
@@ -0,0 +1,127 @@
+//! A buffering logger that keeps the most recent N log records in a bounded
+//! in-memory ring while still forwarding everything to an inner backend
+//! (normally `env_logger`).
+//!
+//! Long captures can scroll the interesting lines — transfer errors and the
+//! worker thread's completion-latency spikes — off the terminal. This logger
+//! retains them so the bridge server or a `--dump-log` flag can report the
+//! tail of diagnostics after a `completion.status.is_err()` abort, without the
+//! user having to reproduce the run under `RUST_LOG=debug`.
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use log::{Level, Log, Metadata, Record};
+
+/// One retained log record, flattened so it can outlive the `log::Record` it
+/// was formatted from.
+#[derive(Debug, Clone)]
+pub struct LogRecord {
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+/// The shared ring of recent records, cloneable so it can be snapshotted from
+/// another thread (e.g. the bridge server).
+#[derive(Debug, Clone)]
+pub struct LogBuffer {
+    records: Arc<Mutex<VecDeque<LogRecord>>>,
+    capacity: usize,
+}
+
+impl LogBuffer {
+    /// Create a ring retaining at most `capacity` records.
+    pub fn new(capacity: usize) -> LogBuffer {
+        LogBuffer {
+            records: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+        }
+    }
+
+    fn push(&self, record: LogRecord) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut records = self.records.lock().unwrap();
+        if records.len() == self.capacity {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+
+    /// Snapshot the retained records, oldest first, without clearing them.
+    pub fn snapshot(&self) -> Vec<LogRecord> {
+        self.records.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Take and clear the retained records, oldest first.
+    pub fn drain(&self) -> Vec<LogRecord> {
+        let mut records = self.records.lock().unwrap();
+        records.drain(..).collect()
+    }
+}
+
+/// A [`Log`] implementation that retains records in a [`LogBuffer`] and then
+/// forwards them to an inner backend.
+pub struct BufferingLogger {
+    inner: Box<dyn Log>,
+    buffer: LogBuffer,
+}
+
+impl BufferingLogger {
+    /// Wrap `inner`, retaining up to `capacity` records in the returned
+    /// [`LogBuffer`] in addition to forwarding them.
+    pub fn new(inner: Box<dyn Log>, capacity: usize) -> (BufferingLogger, LogBuffer) {
+        let buffer = LogBuffer::new(capacity);
+        (
+            BufferingLogger {
+                inner,
+                buffer: buffer.clone(),
+            },
+            buffer,
+        )
+    }
+
+    /// Install `self` as the global logger and return the shared buffer.
+    ///
+    /// The global max level is raised to `Trace` so the ring can observe (and
+    /// retain) records the inner backend would otherwise filter out — the whole
+    /// point is to keep the worker thread's debug timing for post-mortem
+    /// analysis even when `RUST_LOG` leaves the backend at a higher level. The
+    /// inner backend still applies its own filter when forwarding.
+    pub fn init(self) -> Result<LogBuffer, log::SetLoggerError> {
+        let buffer = self.buffer.clone();
+        log::set_boxed_logger(Box::new(self))?;
+        log::set_max_level(log::LevelFilter::Trace);
+        Ok(buffer)
+    }
+}
+
+impl Log for BufferingLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        // Retain every record in the ring, independent of the inner backend's
+        // filter: the debug timing we want for post-mortem analysis lives below
+        // the default `env_logger` level, so gating retention on
+        // `inner.enabled` would silently drop exactly the data this logger
+        // exists to keep. The inner backend still applies its own filter.
+        self.buffer.push(LogRecord {
+            level: record.level(),
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+        });
+        if self.inner.enabled(record.metadata()) {
+            self.inner.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}